@@ -0,0 +1,201 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use holochain_runtime::NetworkConfig;
+use serde::Deserialize;
+use url2::Url2;
+
+/// Network-level defaults that can be set globally in the config file and
+/// overridden per app.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct NetworkDefaults {
+    pub bootstrap_url: Option<String>,
+    pub signal_url: Option<String>,
+    pub full_arc: Option<bool>,
+    pub target_arc_factor: Option<u32>,
+}
+
+/// A single hApp to maintain an always-online node for, as declared in the
+/// `[[apps]]` section of the config file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AppConfig {
+    pub happ_bundle_path: PathBuf,
+    pub installed_app_id: Option<String>,
+    #[serde(flatten)]
+    pub network: NetworkDefaults,
+}
+
+/// Top-level shape of `--config <file.toml>`: global network defaults plus
+/// the list of apps to install, each of which may override those defaults.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FileConfig {
+    #[serde(flatten)]
+    pub defaults: NetworkDefaults,
+    #[serde(default)]
+    pub apps: Vec<AppConfig>,
+}
+
+pub fn read_config(path: &Path) -> Result<FileConfig> {
+    let contents = std::fs::read_to_string(path)?;
+    Ok(toml::from_str(&contents)?)
+}
+
+/// A fully-resolved app to install: the bundle to use, its installed-app-id
+/// override (if any), and the network it should run on.
+#[derive(Debug, Clone)]
+pub struct ResolvedApp {
+    pub happ_bundle_path: PathBuf,
+    pub installed_app_id: Option<String>,
+    pub network_config: NetworkConfig,
+}
+
+/// Builds the `NetworkConfig` for a single app from the CLI flags, its own
+/// overrides, and the config file's global defaults, in that priority order
+/// (CLI flags win, since they're meant for quick one-off overrides).
+pub fn resolved_network_config(
+    app: &NetworkDefaults,
+    file_defaults: &NetworkDefaults,
+    cli_bootstrap_url: &Option<String>,
+    cli_signal_url: &Option<String>,
+    cli_full_arc: Option<bool>,
+    cli_target_arc_factor: &Option<u32>,
+) -> NetworkConfig {
+    let bootstrap_url = cli_bootstrap_url
+        .clone()
+        .or_else(|| app.bootstrap_url.clone())
+        .or_else(|| file_defaults.bootstrap_url.clone());
+    let signal_url = cli_signal_url
+        .clone()
+        .or_else(|| app.signal_url.clone())
+        .or_else(|| file_defaults.signal_url.clone());
+    let target_arc_factor = cli_target_arc_factor
+        .or(app.target_arc_factor)
+        .or(file_defaults.target_arc_factor);
+    let full_arc = cli_full_arc
+        .or(app.full_arc)
+        .or(file_defaults.full_arc)
+        .unwrap_or(true);
+
+    let mut config = NetworkConfig::default();
+    if let Some(bootstrap_url) = bootstrap_url {
+        config.bootstrap_url = Url2::parse(bootstrap_url);
+    }
+    if let Some(signal_url) = signal_url {
+        config.signal_url = Url2::parse(signal_url);
+    }
+    config.target_arc_factor = match target_arc_factor {
+        Some(factor) => factor,
+        None if full_arc => u32::MAX,
+        None => config.target_arc_factor,
+    };
+
+    config
+}
+
+/// A `NetworkConfig` isn't hashable/comparable, so always-online nodes that
+/// straddle multiple networks are grouped by this key: apps sharing a key
+/// share a conductor.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NetworkConfigKey {
+    pub bootstrap_url: String,
+    pub signal_url: String,
+    pub target_arc_factor: u32,
+}
+
+impl From<&NetworkConfig> for NetworkConfigKey {
+    fn from(config: &NetworkConfig) -> Self {
+        Self {
+            bootstrap_url: config.bootstrap_url.to_string(),
+            signal_url: config.signal_url.to_string(),
+            target_arc_factor: config.target_arc_factor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cli_full_arc_overrides_file_defaults() {
+        let app = NetworkDefaults::default();
+        let file_defaults = NetworkDefaults {
+            full_arc: Some(true),
+            ..Default::default()
+        };
+
+        let config = resolved_network_config(&app, &file_defaults, &None, &None, Some(false), &None);
+
+        assert_ne!(config.target_arc_factor, u32::MAX);
+    }
+
+    #[test]
+    fn cli_full_arc_overrides_app_config() {
+        let app = NetworkDefaults {
+            full_arc: Some(false),
+            ..Default::default()
+        };
+        let file_defaults = NetworkDefaults::default();
+
+        let config = resolved_network_config(&app, &file_defaults, &None, &None, Some(true), &None);
+
+        assert_eq!(config.target_arc_factor, u32::MAX);
+    }
+
+    #[test]
+    fn app_full_arc_overrides_file_defaults_when_cli_unset() {
+        let app = NetworkDefaults {
+            full_arc: Some(true),
+            ..Default::default()
+        };
+        let file_defaults = NetworkDefaults {
+            full_arc: Some(false),
+            ..Default::default()
+        };
+
+        let config = resolved_network_config(&app, &file_defaults, &None, &None, None, &None);
+
+        assert_eq!(config.target_arc_factor, u32::MAX);
+    }
+
+    #[test]
+    fn defaults_to_full_arc_when_nothing_set() {
+        let defaults = NetworkDefaults::default();
+
+        let config = resolved_network_config(&defaults, &defaults, &None, &None, None, &None);
+
+        assert_eq!(config.target_arc_factor, u32::MAX);
+    }
+
+    #[test]
+    fn cli_bootstrap_url_overrides_app_and_file() {
+        let app = NetworkDefaults {
+            bootstrap_url: Some("https://app.example".to_string()),
+            ..Default::default()
+        };
+        let file_defaults = NetworkDefaults {
+            bootstrap_url: Some("https://file.example".to_string()),
+            ..Default::default()
+        };
+
+        let config = resolved_network_config(
+            &app,
+            &file_defaults,
+            &Some("https://cli.example".to_string()),
+            &None,
+            None,
+            &None,
+        );
+
+        assert!(config.bootstrap_url.to_string().contains("cli.example"));
+    }
+
+    #[test]
+    fn cli_target_arc_factor_overrides_full_arc() {
+        let defaults = NetworkDefaults::default();
+
+        let config = resolved_network_config(&defaults, &defaults, &None, &None, Some(true), &Some(7));
+
+        assert_eq!(config.target_arc_factor, 7);
+    }
+}
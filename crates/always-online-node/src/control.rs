@@ -0,0 +1,232 @@
+use std::net::SocketAddr;
+
+use anyhow::Result;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use socket2::{Domain, Protocol, Socket, Type};
+use tokio::net::TcpListener;
+
+use crate::AlwaysOnlineNode;
+
+/// Shared state handed to every request/RPC call made against the control
+/// server.
+#[derive(Clone)]
+struct ControlState {
+    node: AlwaysOnlineNode,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcRequest {
+    #[allow(dead_code)]
+    jsonrpc: String,
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonRpcResponse {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<JsonRpcError>,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonRpcError {
+    code: i32,
+    message: String,
+}
+
+/// Runs the admin control server: a JSON-RPC-over-WebSocket interface for
+/// querying node status and managing installed apps at runtime, plus a
+/// plain `GET /healthz` endpoint multiplexed onto the same listener.
+///
+/// Binds a single dual-stack socket so both IPv4 and IPv6 clients can
+/// connect to `listen_addr` without the operator needing two listeners.
+///
+/// There is no authentication on this server: `install_app` reads and
+/// installs an arbitrary filesystem path as a hApp, and `uninstall_app`
+/// removes any app it names. `listen_addr` must only ever be bound to
+/// loopback or another trusted, private network — never exposed to an
+/// untrusted network.
+pub async fn run_control_server(listen_addr: SocketAddr, node: AlwaysOnlineNode) -> Result<()> {
+    let state = ControlState { node };
+
+    let app = Router::new()
+        .route("/healthz", get(healthz))
+        .route("/", get(rpc_upgrade))
+        .with_state(state);
+
+    let listener = bind_dual_stack(listen_addr)?;
+
+    log::info!("Control server listening on {listen_addr} (dual-stack)");
+
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
+
+/// Binds `addr` on a socket with `IPV6_V6ONLY` disabled, so a single listener
+/// accepts both IPv4 and IPv6 connections. `addr` should be an IPv6 address
+/// (e.g. `[::]:8000`) for the dual-stack mapping to apply.
+fn bind_dual_stack(addr: SocketAddr) -> Result<TcpListener> {
+    let domain = if addr.is_ipv6() {
+        Domain::IPV6
+    } else {
+        Domain::IPV4
+    };
+
+    let socket = Socket::new(domain, Type::STREAM, Some(Protocol::TCP))?;
+    if addr.is_ipv6() {
+        socket.set_only_v6(false)?;
+    }
+    socket.set_reuse_address(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(128)?;
+    socket.set_nonblocking(true)?;
+
+    let listener = std::net::TcpListener::from(socket);
+    Ok(TcpListener::from_std(listener)?)
+}
+
+/// `GET /healthz` returns 200 iff at least one worker has ticked and every
+/// worker that has ticked is `Active`, 503 otherwise.
+async fn healthz(State(state): State<ControlState>) -> Response {
+    let statuses = state.node.workers().statuses().await;
+    let all_active = !statuses.is_empty()
+        && statuses
+            .values()
+            .all(|status| status.state == crate::worker::WorkerState::Active);
+
+    if all_active {
+        (axum::http::StatusCode::OK, "ok").into_response()
+    } else {
+        (axum::http::StatusCode::SERVICE_UNAVAILABLE, "not ready").into_response()
+    }
+}
+
+async fn rpc_upgrade(
+    ws: WebSocketUpgrade,
+    State(state): State<ControlState>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| handle_rpc_socket(socket, state))
+}
+
+async fn handle_rpc_socket(mut socket: WebSocket, state: ControlState) {
+    while let Some(Ok(message)) = socket.recv().await {
+        let Message::Text(text) = message else {
+            continue;
+        };
+
+        let response = match serde_json::from_str::<JsonRpcRequest>(&text) {
+            Ok(request) => dispatch(request, &state).await,
+            Err(err) => JsonRpcResponse {
+                jsonrpc: "2.0",
+                id: Value::Null,
+                result: None,
+                error: Some(JsonRpcError {
+                    code: -32700,
+                    message: format!("Parse error: {err}"),
+                }),
+            },
+        };
+
+        let Ok(text) = serde_json::to_string(&response) else {
+            continue;
+        };
+        if socket.send(Message::Text(text)).await.is_err() {
+            break;
+        }
+    }
+}
+
+async fn dispatch(request: JsonRpcRequest, state: &ControlState) -> JsonRpcResponse {
+    let result = match request.method.as_str() {
+        "status" => status(state).await,
+        "install_app" => install_app(request.params, state).await,
+        "uninstall_app" => uninstall_app(request.params, state).await,
+        other => Err(anyhow::anyhow!("Unknown method '{other}'")),
+    };
+
+    match result {
+        Ok(result) => JsonRpcResponse {
+            jsonrpc: "2.0",
+            id: request.id,
+            result: Some(result),
+            error: None,
+        },
+        Err(err) => JsonRpcResponse {
+            jsonrpc: "2.0",
+            id: request.id,
+            result: None,
+            error: Some(JsonRpcError {
+                code: -32000,
+                message: format!("{err:?}"),
+            }),
+        },
+    }
+}
+
+async fn status(state: &ControlState) -> Result<Value> {
+    let app_ids = state.node.installed_apps().await;
+    let workers = state.node.workers().statuses().await;
+
+    let workers: std::collections::HashMap<_, _> = workers
+        .into_iter()
+        .map(|(name, status)| {
+            (
+                name,
+                serde_json::json!({
+                    "state": format!("{:?}", status.state),
+                    "error_count": status.error_count,
+                    "last_tick_secs_ago": status.last_tick_at.elapsed().as_secs(),
+                }),
+            )
+        })
+        .collect();
+
+    Ok(serde_json::json!({ "app_ids": app_ids, "workers": workers }))
+}
+
+#[derive(Debug, Deserialize)]
+struct InstallAppParams {
+    app_id: String,
+    happ_bundle_path: std::path::PathBuf,
+    /// Which of this node's conductors (as grouped by network config at
+    /// launch) to install onto. Defaults to the first if unset.
+    #[serde(default)]
+    network_index: Option<usize>,
+}
+
+async fn install_app(params: Value, state: &ControlState) -> Result<Value> {
+    let params: InstallAppParams = serde_json::from_value(params)?;
+
+    let installed_app_id = state
+        .node
+        .install_bundle(params.happ_bundle_path, Some(params.app_id), params.network_index)
+        .await?;
+
+    Ok(serde_json::json!({ "installed_app_id": installed_app_id }))
+}
+
+#[derive(Debug, Deserialize)]
+struct UninstallAppParams {
+    app_id: String,
+}
+
+async fn uninstall_app(params: Value, state: &ControlState) -> Result<Value> {
+    let params: UninstallAppParams = serde_json::from_value(params)?;
+
+    state.node.uninstall_app(&params.app_id).await?;
+
+    Ok(serde_json::json!({ "uninstalled_app_id": params.app_id }))
+}
@@ -0,0 +1,457 @@
+//! Library powering the `always-online-node` binary: a builder for running
+//! one or more Holochain conductors that install hApp bundles and keep
+//! serving their DHTs. Embedding services can spawn nodes directly through
+//! [`AlwaysOnlineNodeBuilder`] instead of shelling out to the binary — e.g.
+//! from integration tests, or alongside an app backend.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use holochain_client::{AdminWebsocket, ZomeCallTarget};
+use holochain_conductor_api::CellInfo;
+use holochain_runtime::*;
+use holochain_types::prelude::*;
+use tokio::sync::RwLock;
+
+pub mod config;
+pub mod control;
+mod retry;
+pub mod watch;
+pub mod worker;
+
+use config::{NetworkConfigKey, ResolvedApp};
+use worker::{AppHealthWorker, WorkerRegistry};
+
+/// Default cap on install/init retry attempts; overridable via
+/// [`AlwaysOnlineNodeBuilder::install_max_retries`].
+pub const DEFAULT_INSTALL_MAX_RETRIES: u32 = 10;
+
+/// Builds an [`AlwaysOnlineNode`]: a data dir, the apps to install (each with
+/// its own resolved network config), and an optional control-server address.
+pub struct AlwaysOnlineNodeBuilder {
+    data_dir: PathBuf,
+    apps: Vec<ResolvedApp>,
+    control_listen: Option<std::net::SocketAddr>,
+    install_max_retries: u32,
+}
+
+impl AlwaysOnlineNodeBuilder {
+    pub fn new(data_dir: PathBuf) -> Self {
+        Self {
+            data_dir,
+            apps: Vec::new(),
+            control_listen: None,
+            install_max_retries: DEFAULT_INSTALL_MAX_RETRIES,
+        }
+    }
+
+    /// Adds an app to install, with its own resolved network config.
+    pub fn with_app(mut self, app: ResolvedApp) -> Self {
+        self.apps.push(app);
+        self
+    }
+
+    pub fn with_apps(mut self, apps: impl IntoIterator<Item = ResolvedApp>) -> Self {
+        self.apps.extend(apps);
+        self
+    }
+
+    /// Runs an admin control server (JSON-RPC over WebSocket, plus
+    /// `GET /healthz`) on `addr` once the node is launched. `addr` is
+    /// unauthenticated and must be loopback or another trusted network only;
+    /// see [`control::run_control_server`].
+    pub fn control_listen(mut self, addr: std::net::SocketAddr) -> Self {
+        self.control_listen = Some(addr);
+        self
+    }
+
+    /// Caps the number of install/init attempts made per app before giving
+    /// up, in case the bootstrap/signal servers aren't reachable yet.
+    pub fn install_max_retries(mut self, max_retries: u32) -> Self {
+        self.install_max_retries = max_retries;
+        self
+    }
+
+    /// Launches the conductor(s) and installs all configured apps, grouping
+    /// apps by their resolved network config since a conductor is launched
+    /// with a single, fixed `NetworkConfig`.
+    pub async fn launch(self) -> Result<AlwaysOnlineNode> {
+        if self.data_dir.exists() {
+            if std::fs::read_dir(&self.data_dir).is_err() {
+                return Err(anyhow!("The given data dir is not a directory."));
+            }
+        } else {
+            std::fs::create_dir_all(&self.data_dir)?;
+        }
+
+        let mut groups: Vec<(NetworkConfigKey, NetworkConfig, Vec<ResolvedApp>)> = Vec::new();
+        for app in self.apps {
+            let key = NetworkConfigKey::from(&app.network_config);
+            match groups.iter_mut().find(|(k, _, _)| *k == key) {
+                Some((_, _, apps)) => apps.push(app),
+                None => groups.push((key, app.network_config.clone(), vec![app])),
+            }
+        }
+
+        let app_ids: Arc<RwLock<Vec<String>>> = Arc::new(RwLock::new(Vec::new()));
+        let worker_registry = WorkerRegistry::new();
+        let mut runtimes = Vec::new();
+
+        for (index, (_key, network_config, apps)) in groups.into_iter().enumerate() {
+            log::info!(
+                "Effective DHT arc coverage for network {index}: target_arc_factor={} ({})",
+                network_config.target_arc_factor,
+                if network_config.target_arc_factor == u32::MAX {
+                    "full replica"
+                } else {
+                    "partial-arc peer"
+                }
+            );
+
+            let runtime_data_dir = self.data_dir.join(format!("network-{index}"));
+            std::fs::create_dir_all(&runtime_data_dir)?;
+            let runtime_config = HolochainRuntimeConfig::new(runtime_data_dir, network_config);
+
+            let runtime = HolochainRuntime::launch(vec_to_locked(vec![]), runtime_config).await?;
+            let admin_ws = runtime.admin_websocket().await?;
+
+            for app in apps {
+                install_and_init(
+                    &runtime,
+                    &admin_ws,
+                    app.happ_bundle_path,
+                    app.installed_app_id,
+                    self.install_max_retries,
+                )
+                .await?;
+            }
+
+            let installed_apps = admin_ws
+                .list_apps(None)
+                .await
+                .map_err(|err| anyhow!("{err:?}"))?;
+            for installed in installed_apps {
+                let app_id = installed.installed_app_id;
+                app_ids.write().await.push(app_id.clone());
+                worker_registry.spawn(
+                    AppHealthWorker::new(app_id, admin_ws.clone()),
+                    Duration::from_secs(30),
+                );
+            }
+
+            runtimes.push(runtime);
+        }
+
+        log::info!(
+            "Always online node running for DNAs {:?}",
+            app_ids.read().await
+        );
+
+        let node = AlwaysOnlineNode {
+            runtimes,
+            app_ids,
+            worker_registry,
+            install_max_retries: self.install_max_retries,
+        };
+
+        if let Some(control_listen) = self.control_listen {
+            let node = node.clone();
+            tokio::spawn(async move {
+                if let Err(err) = control::run_control_server(control_listen, node).await {
+                    log::error!("Control server exited with error: {err:?}");
+                }
+            });
+        }
+
+        Ok(node)
+    }
+}
+
+/// A running always-online node: one or more Holochain conductors, each
+/// serving the DHTs of the apps installed on it. Cheaply cloneable, like the
+/// underlying conductor handles it wraps.
+#[derive(Clone)]
+pub struct AlwaysOnlineNode {
+    runtimes: Vec<HolochainRuntime>,
+    app_ids: Arc<RwLock<Vec<String>>>,
+    worker_registry: WorkerRegistry,
+    install_max_retries: u32,
+}
+
+impl AlwaysOnlineNode {
+    /// The `installed_app_id`s currently running on this node, across all of
+    /// its conductors.
+    pub async fn installed_apps(&self) -> Vec<String> {
+        self.app_ids.read().await.clone()
+    }
+
+    /// The background health-monitoring worker registry for this node.
+    pub fn workers(&self) -> WorkerRegistry {
+        self.worker_registry.clone()
+    }
+
+    /// Installs an additional bundle at runtime, onto the conductor for
+    /// `network_index` (as assigned by [`AlwaysOnlineNodeBuilder::launch`]'s
+    /// network-config grouping), or this node's first conductor if
+    /// `network_index` is `None`.
+    pub async fn install_bundle(
+        &self,
+        happ_bundle_path: PathBuf,
+        installed_app_id: Option<String>,
+        network_index: Option<usize>,
+    ) -> Result<String> {
+        let index = network_index.unwrap_or(0);
+        let runtime = self
+            .runtimes
+            .get(index)
+            .ok_or_else(|| anyhow!("No conductor for network index {index}"))?;
+        let admin_ws = runtime.admin_websocket().await?;
+
+        let app_id = install_and_init(
+            runtime,
+            &admin_ws,
+            happ_bundle_path,
+            installed_app_id,
+            self.install_max_retries,
+        )
+        .await?;
+
+        self.app_ids.write().await.push(app_id.clone());
+        self.worker_registry.spawn(
+            AppHealthWorker::new(app_id.clone(), admin_ws),
+            Duration::from_secs(30),
+        );
+
+        Ok(app_id)
+    }
+
+    /// The index into this node's conductors (as passed to
+    /// [`AlwaysOnlineNode::install_bundle`]) that has `app_id` installed, if
+    /// any.
+    pub async fn network_index_of(&self, app_id: &str) -> Result<Option<usize>> {
+        for (index, runtime) in self.runtimes.iter().enumerate() {
+            let admin_ws = runtime.admin_websocket().await?;
+            let installed = admin_ws
+                .list_apps(None)
+                .await
+                .map_err(|err| anyhow!("{err:?}"))?;
+            if installed.iter().any(|app| app.installed_app_id == app_id) {
+                return Ok(Some(index));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Finds whichever conductor actually has `app_id` installed, rather than
+    /// assuming it lives on the first one: once apps are grouped across more
+    /// than one network, an app can live on any of this node's conductors.
+    async fn runtime_and_admin_for_app(
+        &self,
+        app_id: &str,
+    ) -> Result<(&HolochainRuntime, AdminWebsocket)> {
+        let index = self
+            .network_index_of(app_id)
+            .await?
+            .ok_or_else(|| anyhow!("App '{app_id}' not found on any conductor"))?;
+        let runtime = &self.runtimes[index];
+        let admin_ws = runtime.admin_websocket().await?;
+        Ok((runtime, admin_ws))
+    }
+
+    /// Disables an installed app on whichever conductor has it, e.g. ahead of
+    /// installing a newer version of it under a different app id. The app's
+    /// cells and data are kept, unlike [`AlwaysOnlineNode::uninstall_app`].
+    pub async fn disable_app(&self, app_id: &str) -> Result<()> {
+        let (_runtime, admin_ws) = self.runtime_and_admin_for_app(app_id).await?;
+
+        admin_ws
+            .disable_app(app_id.to_string())
+            .await
+            .map_err(|err| anyhow!("{err:?}"))?;
+
+        self.app_ids.write().await.retain(|id| id != app_id);
+
+        Ok(())
+    }
+
+    /// Uninstalls an app from whichever conductor has it, removing its cells
+    /// and data outright, unlike [`AlwaysOnlineNode::disable_app`].
+    pub async fn uninstall_app(&self, app_id: &str) -> Result<()> {
+        let (_runtime, admin_ws) = self.runtime_and_admin_for_app(app_id).await?;
+
+        admin_ws
+            .uninstall_app(app_id.to_string())
+            .await
+            .map_err(|err| anyhow!("{err:?}"))?;
+
+        self.app_ids.write().await.retain(|id| id != app_id);
+
+        Ok(())
+    }
+
+    /// Gracefully shuts down every conductor this node is running.
+    pub async fn shutdown(&self) -> Result<()> {
+        for runtime in &self.runtimes {
+            runtime.shutdown().await?;
+        }
+        Ok(())
+    }
+}
+
+/// Installs `happ_bundle_path` on `runtime` if it isn't already installed,
+/// then calls `init` on every cell's first coordinator zome. Returns the
+/// resulting `installed_app_id`.
+///
+/// The install-and-init sequence is retried with exponential backoff (base
+/// 1s, up to 5 min, ±25% jitter), making at most `max_retries` attempts in
+/// all (the first one included), since a transient
+/// failure (e.g. the bootstrap/signal servers not being reachable yet) is
+/// expected on an unattended always-online daemon that may start before its
+/// infrastructure does. An already-installed app or already-initialized cell
+/// is treated as success rather than an error, so retries are idempotent.
+async fn install_and_init(
+    runtime: &HolochainRuntime,
+    admin_ws: &AdminWebsocket,
+    happ_bundle_path: PathBuf,
+    installed_app_id: Option<String>,
+    max_retries: u32,
+) -> Result<String> {
+    let app_id = match installed_app_id {
+        Some(app_id) => app_id,
+        None => read_from_file(&happ_bundle_path)
+            .await?
+            .manifest()
+            .app_name()
+            .to_string(),
+    };
+
+    retry::with_backoff(
+        max_retries,
+        Duration::from_secs(1),
+        Duration::from_secs(5 * 60),
+        |attempt| {
+            let app_id = app_id.clone();
+            let happ_bundle_path = happ_bundle_path.clone();
+            async move {
+                if attempt > 1 {
+                    log::info!("Retrying install of app '{app_id}', attempt {attempt}");
+                }
+                install_and_init_once(runtime, admin_ws, happ_bundle_path, app_id).await
+            }
+        },
+    )
+    .await?;
+
+    Ok(app_id)
+}
+
+async fn install_and_init_once(
+    runtime: &HolochainRuntime,
+    admin_ws: &AdminWebsocket,
+    happ_bundle_path: PathBuf,
+    app_id: String,
+) -> Result<()> {
+    let installed_apps = admin_ws
+        .list_apps(None)
+        .await
+        .map_err(|err| anyhow!("{err:?}"))?;
+
+    // Already installed: still drive the init loop below with its existing
+    // cell_info, rather than bailing out, so a retry after a transient `init`
+    // failure actually retries `init` instead of reporting false success.
+    let app_info = match installed_apps
+        .into_iter()
+        .find(|installed| installed.installed_app_id == app_id)
+    {
+        Some(app_info) => app_info,
+        None => {
+            let happ_bundle = read_from_file(&happ_bundle_path).await?;
+            match runtime
+                .install_app(app_id.clone(), happ_bundle, None, None, None)
+                .await
+            {
+                Ok(app_info) => app_info,
+                Err(err) if is_already_installed(&err) => {
+                    log::info!("App '{app_id}' was already installed, treating as success");
+                    admin_ws
+                        .list_apps(None)
+                        .await
+                        .map_err(|err| anyhow!("{err:?}"))?
+                        .into_iter()
+                        .find(|installed| installed.installed_app_id == app_id)
+                        .ok_or_else(|| anyhow!("App '{app_id}' vanished after install"))?
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    };
+
+    let app_ws = runtime
+        .app_websocket(app_id.clone(), holochain_types::websocket::AllowedOrigins::Any)
+        .await?;
+
+    for (_role, cell_infos) in app_info.cell_info {
+        for cell_info in cell_infos {
+            let Some(cell_id) = cell_id(&cell_info) else {
+                continue;
+            };
+            let dna_def = admin_ws
+                .get_dna_definition(cell_id.dna_hash().clone())
+                .await
+                .map_err(|err| anyhow!("{err:?}"))?;
+
+            let Some(first_zome) = dna_def.coordinator_zomes.first() else {
+                continue;
+            };
+
+            let result = app_ws
+                .call_zome(
+                    ZomeCallTarget::CellId(cell_id),
+                    first_zome.0.clone(),
+                    "init".into(),
+                    ExternIO::encode(())?,
+                )
+                .await;
+
+            if let Err(err) = result {
+                if is_already_initialized(&err) {
+                    log::info!("Cell for app '{app_id}' was already initialized, treating as success");
+                    continue;
+                }
+                return Err(anyhow!("{:?}", err));
+            }
+        }
+    }
+
+    log::info!("Installed app for hApp {}", app_id);
+
+    Ok(())
+}
+
+/// Best-effort check for an "already installed" error, since the conductor
+/// API reports this as an error rather than a distinct, matchable variant.
+fn is_already_installed(err: &impl std::fmt::Debug) -> bool {
+    format!("{err:?}").to_lowercase().contains("already installed")
+}
+
+/// Best-effort check for an "already initialized" zome call error.
+fn is_already_initialized(err: &impl std::fmt::Debug) -> bool {
+    format!("{err:?}").to_lowercase().contains("already initialized")
+}
+
+pub(crate) async fn read_from_file(happ_bundle_path: &PathBuf) -> Result<AppBundle> {
+    mr_bundle::Bundle::read_from_file(happ_bundle_path)
+        .await
+        .map(Into::into)
+        .map_err(Into::into)
+}
+
+fn cell_id(cell_info: &CellInfo) -> Option<CellId> {
+    match cell_info {
+        CellInfo::Provisioned(provisioned) => Some(provisioned.cell_id.clone()),
+        CellInfo::Cloned(cloned) => Some(cloned.cell_id.clone()),
+        CellInfo::Stem(_) => None,
+    }
+}
@@ -1,48 +1,114 @@
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use clap::Parser;
 use env_logger::Builder;
-use holochain_client::ZomeCallTarget;
-use holochain_conductor_api::CellInfo;
-use holochain_runtime::*;
-use holochain_types::prelude::*;
 use log::Level;
 use std::io::Write;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::time::Duration;
-use url2::Url2;
+
+use always_online_node::config::{self, resolved_network_config, NetworkDefaults, ResolvedApp};
+use always_online_node::AlwaysOnlineNodeBuilder;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
-    /// hApp bundles for which to maintain always online nodes
+    /// hApp bundles for which to maintain always online nodes. Ignored if
+    /// `--config` is given.
     happ_bundles_paths: Vec<PathBuf>,
 
     /// Directory to store all holochain data
     #[arg(long)]
     data_dir: PathBuf,
 
+    /// TOML file declaring the apps to run and their per-app network
+    /// overrides. See `always_online_node::config::FileConfig`. CLI flags
+    /// below still apply and take precedence over the file, for quick
+    /// one-off overrides.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
     #[arg(long)]
     bootstrap_url: Option<String>,
 
     #[arg(long)]
     signal_url: Option<String>,
+
+    /// Claim the full DHT arc, holding and serving every record rather than
+    /// just a partial slice of it. This is what makes this node "always online":
+    /// peers can always fetch data from it. Defaults to on; pass explicitly
+    /// to override a config file's `full_arc` for a one-off run.
+    #[arg(long)]
+    full_arc: Option<bool>,
+
+    /// Override the target storage arc factor directly. Takes precedence over
+    /// `--full-arc` when set.
+    #[arg(long)]
+    target_arc_factor: Option<u32>,
+
+    /// Address to bind the admin control server (JSON-RPC over WebSocket,
+    /// plus a plain `GET /healthz`) on. Accepts both IPv4 and IPv6 clients
+    /// on a single dual-stack socket. If unset, no control server is run.
+    ///
+    /// This server has no authentication: bind it to loopback or another
+    /// trusted, private network only, never to an untrusted network.
+    #[arg(long)]
+    control_listen: Option<SocketAddr>,
+
+    /// Maximum number of install/init attempts per app before giving up, in
+    /// case the bootstrap/signal servers aren't reachable yet.
+    #[arg(long, default_value_t = always_online_node::DEFAULT_INSTALL_MAX_RETRIES)]
+    install_max_retries: u32,
+
+    /// Keep watching the hApp bundle paths for changes, installing upgrades
+    /// without restarting the process.
+    #[arg(long)]
+    watch: bool,
 }
 
-fn network_config(bootstrap_url: Option<Url2>, signal_url: Option<Url2>) -> NetworkConfig {
-    let mut config = NetworkConfig::default();
+/// Resolves the set of apps to install, and the network each should run on,
+/// from either `--config` or the flat CLI args.
+fn resolve_apps(args: &Args) -> Result<Vec<ResolvedApp>> {
+    if let Some(config_path) = &args.config {
+        let file_config = config::read_config(config_path)?;
 
-    if let Some(bootstrap_url) = bootstrap_url {
-        config.bootstrap_url = bootstrap_url;
-    }
-    if let Some(signal_url) = signal_url {
-        config.signal_url = signal_url;
+        return Ok(file_config
+            .apps
+            .iter()
+            .map(|app| ResolvedApp {
+                happ_bundle_path: app.happ_bundle_path.clone(),
+                installed_app_id: app.installed_app_id.clone(),
+                network_config: resolved_network_config(
+                    &app.network,
+                    &file_config.defaults,
+                    &args.bootstrap_url,
+                    &args.signal_url,
+                    args.full_arc,
+                    &args.target_arc_factor,
+                ),
+            })
+            .collect());
     }
 
-    // TODO: change dht storage arc factor?
-    // config.target_arc_factor = u32::MAX;
+    let network_config = resolved_network_config(
+        &NetworkDefaults::default(),
+        &NetworkDefaults::default(),
+        &args.bootstrap_url,
+        &args.signal_url,
+        args.full_arc,
+        &args.target_arc_factor,
+    );
 
-    config
+    Ok(args
+        .happ_bundles_paths
+        .iter()
+        .map(|happ_bundle_path| ResolvedApp {
+            happ_bundle_path: happ_bundle_path.clone(),
+            installed_app_id: None,
+            network_config: network_config.clone(),
+        })
+        .collect())
 }
 
 fn log_level() -> Level {
@@ -75,103 +141,51 @@ async fn main() -> Result<()> {
         .init();
     set_wasm_level();
 
-    let data_dir = args.data_dir;
-    if data_dir.exists() {
-        if !std::fs::read_dir(&data_dir).is_ok() {
-            return Err(anyhow!("The given data dir is not a directory."));
-        };
-    } else {
-        std::fs::create_dir_all(data_dir.clone())?;
-    }
-
-    let network_config = network_config(
-        args.bootstrap_url.map(Url2::parse),
-        args.signal_url.map(Url2::parse),
-    );
-
-    let config = HolochainRuntimeConfig::new(data_dir.clone(), network_config.clone());
-
-    let runtime = HolochainRuntime::launch(vec_to_locked(vec![]), config).await?;
-    let admin_ws = runtime.admin_websocket().await?;
-
-    let installed_apps = admin_ws
-        .list_apps(None)
-        .await
-        .map_err(|err| anyhow!("{err:?}"))?;
-
-    let mut app_ids: Vec<String> = installed_apps
+    let resolved_apps = resolve_apps(&args)?;
+    // Take this before `resolved_apps` is moved into the builder: the set of
+    // bundles actually being run, not the raw CLI positional field, which
+    // `resolve_apps` ignores entirely when `--config` is given.
+    let happ_bundle_paths: Vec<PathBuf> = resolved_apps
         .iter()
-        .map(|app| app.installed_app_id.clone())
+        .map(|app| app.happ_bundle_path.clone())
         .collect();
 
-    for happ_bundle_path in args.happ_bundles_paths {
-        let happ_bundle = read_from_file(&happ_bundle_path).await?;
+    let mut builder = AlwaysOnlineNodeBuilder::new(args.data_dir)
+        .with_apps(resolved_apps)
+        .install_max_retries(args.install_max_retries);
+    if let Some(control_listen) = args.control_listen {
+        builder = builder.control_listen(control_listen);
+    }
 
-        let app_id = happ_bundle.manifest().app_name().to_string();
+    let node = builder.launch().await?;
 
-        if installed_apps
-            .iter()
-            .find(|app| app.installed_app_id.eq(&app_id))
-            .is_none()
-        {
-            let app_info = runtime
-                .install_app(app_id.clone(), happ_bundle, None, None, None)
-                .await?;
-            let app_ws = runtime
-                .app_websocket(
-                    app_id.clone(),
-                    holochain_types::websocket::AllowedOrigins::Any,
-                )
-                .await?;
-
-            for (_role, cell_infos) in app_info.cell_info {
-                for cell_info in cell_infos {
-                    let Some(cell_id) = cell_id(&cell_info) else {
-                        continue;
-                    };
-                    let dna_def = admin_ws
-                        .get_dna_definition(cell_id.dna_hash().clone())
-                        .await
-                        .map_err(|err| anyhow!("{err:?}"))?;
-
-                    let Some(first_zome) = dna_def.coordinator_zomes.first() else {
-                        continue;
-                    };
-
-                    app_ws
-                        .call_zome(
-                            ZomeCallTarget::CellId(cell_id),
-                            first_zome.0.clone(),
-                            "init".into(),
-                            ExternIO::encode(())?,
-                        )
-                        .await
-                        .map_err(|err| anyhow!("{:?}", err))?;
-                }
+    if args.watch {
+        let node = node.clone();
+        tokio::spawn(async move {
+            if let Err(err) = always_online_node::watch::watch_for_upgrades(node, happ_bundle_paths).await
+            {
+                log::error!("hApp bundle watcher exited with error: {err:?}");
             }
-
-            app_ids.push(app_id.clone());
-
-            log::info!("Installed app for hApp {}", app_id);
-        }
+        });
     }
 
-    log::info!("Starting always online node for DNAs {:?}", app_ids);
-
     // wait for a unix signal or ctrl-c instruction to
     // shutdown holochain
-    ctrlc::set_handler(move || {
-        let r = runtime.clone();
-        holochain_util::tokio_helper::block_on(
-            async move {
-                log::info!("Gracefully shutting down conductor...");
-                if let Err(err) = r.shutdown().await {
-                    log::error!("Failed to shutdown conductor: {err:?}.");
-                }
-            },
-            Duration::from_secs(10),
-        )
-        .expect("Failed to block on shutdown.");
+    ctrlc::set_handler({
+        let node = node.clone();
+        move || {
+            let node = node.clone();
+            holochain_util::tokio_helper::block_on(
+                async move {
+                    log::info!("Gracefully shutting down conductor...");
+                    if let Err(err) = node.shutdown().await {
+                        log::error!("Failed to shutdown conductor: {err:?}.");
+                    }
+                },
+                Duration::from_secs(10),
+            )
+            .expect("Failed to block on shutdown.");
+        }
     })?;
 
     // wait for a unix signal or ctrl-c instruction to
@@ -181,18 +195,3 @@ async fn main() -> Result<()> {
 
     Ok(())
 }
-
-async fn read_from_file(happ_bundle_path: &PathBuf) -> Result<AppBundle> {
-    mr_bundle::Bundle::read_from_file(happ_bundle_path)
-        .await
-        .map(Into::into)
-        .map_err(Into::into)
-}
-
-fn cell_id(cell_info: &CellInfo) -> Option<CellId> {
-    match cell_info {
-        CellInfo::Provisioned(provisioned) => Some(provisioned.cell_id.clone()),
-        CellInfo::Cloned(cloned) => Some(cloned.cell_id.clone()),
-        CellInfo::Stem(_) => None,
-    }
-}
@@ -0,0 +1,130 @@
+use std::future::Future;
+use std::time::Duration;
+
+use anyhow::Result;
+use rand::Rng;
+
+/// Retries `f` with exponential backoff (±25% jitter, to avoid a thundering
+/// herd of reconnects) until it succeeds or `max_attempts` is exhausted.
+/// Attempt numbers passed to `f` start at 1.
+pub async fn with_backoff<T, F, Fut>(
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    mut f: F,
+) -> Result<T>
+where
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        match f(attempt).await {
+            Ok(value) => return Ok(value),
+            Err(err) if attempt >= max_attempts => return Err(err),
+            Err(err) => {
+                let delay = next_delay(attempt, base_delay, max_delay);
+                log::warn!(
+                    "Attempt {attempt}/{max_attempts} failed ({err:?}), retrying in {delay:?}"
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+fn next_delay(attempt: u32, base_delay: Duration, max_delay: Duration) -> Duration {
+    let shift = attempt.saturating_sub(1).min(20);
+    let exponential = base_delay.saturating_mul(1u32.checked_shl(shift).unwrap_or(u32::MAX));
+    let capped = exponential.min(max_delay);
+
+    let jitter_fraction: f64 = rand::thread_rng().gen_range(-0.25..=0.25);
+    let jittered_millis = (capped.as_millis() as f64) * (1.0 + jitter_fraction);
+    Duration::from_millis(jittered_millis.max(0.0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn within_jitter(actual: Duration, expected: Duration) -> bool {
+        let actual = actual.as_millis() as f64;
+        let expected = expected.as_millis() as f64;
+        (actual - expected).abs() <= expected * 0.25 + 1.0
+    }
+
+    #[test]
+    fn next_delay_starts_at_base_delay() {
+        let base = Duration::from_secs(1);
+        let delay = next_delay(1, base, Duration::from_secs(60));
+        assert!(
+            within_jitter(delay, base),
+            "first retry should wait ~base_delay, got {delay:?}"
+        );
+    }
+
+    #[test]
+    fn next_delay_doubles_each_attempt() {
+        let base = Duration::from_secs(1);
+        let max = Duration::from_secs(60);
+        assert!(within_jitter(next_delay(2, base, max), Duration::from_secs(2)));
+        assert!(within_jitter(next_delay(3, base, max), Duration::from_secs(4)));
+        assert!(within_jitter(next_delay(4, base, max), Duration::from_secs(8)));
+    }
+
+    #[test]
+    fn next_delay_caps_at_max_delay() {
+        let delay = next_delay(20, Duration::from_secs(1), Duration::from_secs(5));
+        assert!(delay <= Duration::from_millis(5_000 + 1_250));
+    }
+
+    #[tokio::test]
+    async fn with_backoff_returns_first_success_without_retrying() {
+        let attempts = AtomicU32::new(0);
+        let result = with_backoff(3, Duration::from_millis(1), Duration::from_millis(10), |_| {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            async { Ok(42) }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(result, 42);
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn with_backoff_retries_until_success() {
+        let attempts = AtomicU32::new(0);
+        let result = with_backoff(3, Duration::from_millis(1), Duration::from_millis(10), |attempt| {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if attempt < 3 {
+                    Err(anyhow::anyhow!("not yet"))
+                } else {
+                    Ok(attempt)
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(result, 3);
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn with_backoff_gives_up_after_max_attempts() {
+        let attempts = AtomicU32::new(0);
+        let result: Result<()> =
+            with_backoff(2, Duration::from_millis(1), Duration::from_millis(10), |_| {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                async { Err(anyhow::anyhow!("still failing")) }
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+}
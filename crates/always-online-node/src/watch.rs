@@ -0,0 +1,134 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+
+use crate::AlwaysOnlineNode;
+
+/// Watches `happ_bundle_paths` for changes; when a bundle file is modified,
+/// compares it against what's currently installed and, if it changed,
+/// installs the new version (disabling the old one first). Runs until the
+/// channel from the underlying filesystem watcher closes.
+pub async fn watch_for_upgrades(node: AlwaysOnlineNode, happ_bundle_paths: Vec<PathBuf>) -> Result<()> {
+    let (tx, mut rx) = mpsc::channel(16);
+
+    let mut watcher = RecommendedWatcher::new(
+        move |event: notify::Result<Event>| {
+            if let Ok(event) = event {
+                if matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_)) {
+                    let _ = tx.blocking_send(event.paths);
+                }
+            }
+        },
+        notify::Config::default(),
+    )?;
+
+    for path in &happ_bundle_paths {
+        watcher.watch(path, RecursiveMode::NonRecursive)?;
+    }
+    log::info!("Watching {} hApp bundle(s) for upgrades", happ_bundle_paths.len());
+
+    while let Some(paths) = rx.recv().await {
+        for path in paths {
+            if !happ_bundle_paths.iter().any(|watched| watched == &path) {
+                continue;
+            }
+            if let Err(err) = upgrade_if_changed(&node, &path).await {
+                log::error!("Failed to process hot-reload for {path:?}: {err:?}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn upgrade_if_changed(node: &AlwaysOnlineNode, path: &Path) -> Result<()> {
+    let happ_bundle = crate::read_from_file(&path.to_path_buf()).await?;
+    let app_name = happ_bundle.manifest().app_name().to_string();
+    let version = bundle_version(path)?;
+    let new_app_id = format!("{app_name}-{version}");
+
+    let installed = node.installed_apps().await;
+    if installed.iter().any(|id| id == &new_app_id) {
+        return Ok(());
+    }
+
+    let old_app_id = find_old_app_id(&installed, &app_name);
+
+    // Reinstall onto whichever conductor the old version lives on (if any),
+    // so a hot-reload can't silently move a multi-network app to a different
+    // network's conductor.
+    let network_index = match &old_app_id {
+        Some(old_app_id) => node.network_index_of(old_app_id).await?,
+        None => None,
+    };
+
+    if let Some(old_app_id) = &old_app_id {
+        log::info!("Detected new version of app '{app_name}': disabling '{old_app_id}'");
+        node.disable_app(old_app_id).await?;
+    }
+
+    node.install_bundle(path.to_path_buf(), Some(new_app_id.clone()), network_index)
+        .await?;
+    log::info!("Hot-reloaded app '{app_name}' as '{new_app_id}'");
+
+    Ok(())
+}
+
+/// Finds the currently-installed id for `app_name`, if any. The very first
+/// install (before any hot-reload) uses the plain `app_name` as its id, with
+/// no version suffix; later ones are `app_name-<version>`. Match both so the
+/// first reload still finds and disables it.
+fn find_old_app_id(installed: &[String], app_name: &str) -> Option<String> {
+    installed
+        .iter()
+        .find(|id| id.as_str() == app_name || id.starts_with(&format!("{app_name}-")))
+        .cloned()
+}
+
+/// A cheap stand-in for a manifest version: the bundle manifest format here
+/// doesn't carry its own version field, so the file's size and modified time
+/// are hashed together to detect content changes.
+fn bundle_version(path: &Path) -> Result<String> {
+    use std::hash::{Hash, Hasher};
+
+    let metadata = std::fs::metadata(path)?;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    metadata.len().hash(&mut hasher);
+    metadata.modified()?.hash(&mut hasher);
+
+    Ok(format!("{:x}", hasher.finish()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_unsuffixed_first_install() {
+        let installed = vec!["my_app".to_string()];
+        assert_eq!(find_old_app_id(&installed, "my_app"), Some("my_app".to_string()));
+    }
+
+    #[test]
+    fn matches_versioned_later_install() {
+        let installed = vec!["my_app-abc123".to_string()];
+        assert_eq!(
+            find_old_app_id(&installed, "my_app"),
+            Some("my_app-abc123".to_string())
+        );
+    }
+
+    #[test]
+    fn does_not_match_unrelated_app_with_shared_prefix() {
+        let installed = vec!["my_app_other".to_string()];
+        assert_eq!(find_old_app_id(&installed, "my_app"), None);
+    }
+
+    #[test]
+    fn returns_none_when_not_installed() {
+        let installed = vec!["some_other_app".to_string()];
+        assert_eq!(find_old_app_id(&installed, "my_app"), None);
+    }
+}
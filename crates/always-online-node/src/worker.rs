@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use holochain_client::AdminWebsocket;
+use tokio::sync::RwLock;
+
+/// The health of a background worker, as last observed by its `tick`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkerState {
+    /// The worker ticked successfully and observed forward progress.
+    Active,
+    /// The worker ticked successfully but observed no forward progress.
+    Idle,
+    /// The worker's last tick errored out, or found that what it monitors is
+    /// gone (e.g. the app is no longer installed).
+    Dead,
+}
+
+/// A single step of supervised background work. Implementors own whatever
+/// state they need to detect forward progress between ticks (e.g. the last
+/// peer count or gossip round seen).
+#[async_trait::async_trait]
+pub trait Worker: Send + Sync {
+    /// A human-readable name for this worker, used for introspection.
+    fn name(&self) -> &str;
+
+    /// Perform a single health check, returning the resulting state.
+    async fn tick(&mut self) -> Result<WorkerState>;
+}
+
+/// Point-in-time status of a worker, as tracked by the [`WorkerRegistry`].
+#[derive(Debug, Clone)]
+pub struct WorkerStatus {
+    pub state: WorkerState,
+    pub last_tick_at: Instant,
+    pub error_count: u64,
+}
+
+/// Shared registry of all background workers running in this process. The
+/// node spawns one tick task per worker after installation completes, and
+/// the control server reads `statuses()` to report on them.
+#[derive(Clone, Default)]
+pub struct WorkerRegistry {
+    statuses: Arc<RwLock<HashMap<String, WorkerStatus>>>,
+}
+
+impl WorkerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Snapshot the current status of every worker that has ticked at least
+    /// once.
+    pub async fn statuses(&self) -> HashMap<String, WorkerStatus> {
+        self.statuses.read().await.clone()
+    }
+
+    /// Spawn `worker`, ticking it every `interval` until the process exits.
+    pub fn spawn(&self, mut worker: impl Worker + 'static, interval: Duration) {
+        let statuses = self.statuses.clone();
+        let name = worker.name().to_string();
+
+        tokio::spawn(async move {
+            loop {
+                let result = worker.tick().await;
+
+                let mut statuses = statuses.write().await;
+                let status = statuses.entry(name.clone()).or_insert_with(|| WorkerStatus {
+                    state: WorkerState::Idle,
+                    last_tick_at: Instant::now(),
+                    error_count: 0,
+                });
+
+                status.last_tick_at = Instant::now();
+                status.state = match result {
+                    Ok(state) => state,
+                    Err(err) => {
+                        status.error_count += 1;
+                        log::warn!("Worker '{name}' tick failed: {err:?}");
+                        WorkerState::Dead
+                    }
+                };
+                drop(statuses);
+
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+}
+
+/// Polls a single installed app's network state: the number of agents known
+/// across its cells, reported as `Active` when that number changed since the
+/// previous tick and `Idle` when it did not. DHT sync or arc-fill progress is
+/// not measured.
+pub struct AppHealthWorker {
+    app_id: String,
+    admin_ws: AdminWebsocket,
+    last_peer_count: Option<usize>,
+}
+
+impl AppHealthWorker {
+    pub fn new(app_id: String, admin_ws: AdminWebsocket) -> Self {
+        Self {
+            app_id,
+            admin_ws,
+            last_peer_count: None,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Worker for AppHealthWorker {
+    fn name(&self) -> &str {
+        &self.app_id
+    }
+
+    async fn tick(&mut self) -> Result<WorkerState> {
+        let apps = self
+            .admin_ws
+            .list_apps(None)
+            .await
+            .map_err(|err| anyhow::anyhow!("{err:?}"))?;
+
+        let Some(app) = apps.iter().find(|app| app.installed_app_id == self.app_id) else {
+            return Ok(WorkerState::Dead);
+        };
+
+        // Count known peers (agents gossip has discovered) across this app's
+        // cells, rather than the cell count itself, which is fixed at
+        // install time and would never change between ticks.
+        let mut peer_count = 0;
+        for cell_infos in app.cell_info.values() {
+            for cell_info in cell_infos {
+                let Some(cell_id) = crate::cell_id(cell_info) else {
+                    continue;
+                };
+                let agent_infos = self
+                    .admin_ws
+                    .agent_info(Some(cell_id))
+                    .await
+                    .map_err(|err| anyhow::anyhow!("{err:?}"))?;
+                peer_count += agent_infos.len();
+            }
+        }
+
+        let progressed = peer_count_progressed(self.last_peer_count, peer_count);
+        self.last_peer_count = Some(peer_count);
+
+        Ok(if progressed {
+            WorkerState::Active
+        } else {
+            WorkerState::Idle
+        })
+    }
+}
+
+/// Whether `current` represents forward progress over `last` (the prior
+/// tick's peer count, or `None` on the first tick).
+fn peer_count_progressed(last: Option<usize>, current: usize) -> bool {
+    last != Some(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_tick_always_progresses() {
+        assert!(peer_count_progressed(None, 0));
+        assert!(peer_count_progressed(None, 5));
+    }
+
+    #[test]
+    fn unchanged_peer_count_does_not_progress() {
+        assert!(!peer_count_progressed(Some(3), 3));
+    }
+
+    #[test]
+    fn changed_peer_count_progresses() {
+        assert!(peer_count_progressed(Some(3), 4));
+        assert!(peer_count_progressed(Some(3), 2));
+    }
+
+    struct ScriptedWorker {
+        name: String,
+        results: std::collections::VecDeque<Result<WorkerState>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Worker for ScriptedWorker {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        async fn tick(&mut self) -> Result<WorkerState> {
+            self.results
+                .pop_front()
+                .unwrap_or(Ok(WorkerState::Idle))
+        }
+    }
+
+    #[tokio::test]
+    async fn registry_tracks_latest_state_and_error_count() {
+        let registry = WorkerRegistry::new();
+        registry.spawn(
+            ScriptedWorker {
+                name: "scripted".to_string(),
+                results: std::collections::VecDeque::from([
+                    Ok(WorkerState::Active),
+                    Err(anyhow::anyhow!("boom")),
+                    Ok(WorkerState::Idle),
+                ]),
+            },
+            Duration::from_millis(5),
+        );
+
+        // First tick happens immediately on spawn; wait for all three.
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let statuses = registry.statuses().await;
+        let status = statuses.get("scripted").expect("worker should have ticked");
+
+        assert_eq!(status.state, WorkerState::Idle);
+        assert_eq!(status.error_count, 1);
+    }
+}